@@ -19,14 +19,263 @@
 use crate::engine::Engine;
 use log;
 use regex::RegexSet;
-use serde_json::Value;
-use std::collections::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::fmt;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use thirtyfour::prelude::*;
 use tokio::sync::Mutex;
 use url::Url;
 
+/// A source of URLs for the sheduler to hand out, ordered however the implementation sees fit.
+///
+/// `push` records the depth a URL was discovered at (the seed URLs are depth `0`), which
+/// implementations are free to ignore (BFS/DFS) or use for ordering (priority).
+pub trait Frontier: fmt::Debug + Send + Sync {
+    fn push(&mut self, url: Url, depth: u32);
+    fn pop(&mut self) -> Option<Url>;
+    fn is_empty(&self) -> bool;
+    /// How many URLs are currently held, for `State`'s `max_frontier` backpressure.
+    fn len(&self) -> usize;
+    /// Removes and returns every URL currently held, for checkpointing. Order is best-effort:
+    /// exact for stack/queue frontiers, but a heap-backed frontier only guarantees the same
+    /// URLs come back out, not the same tie-break order.
+    fn drain(&mut self) -> Vec<Url>;
+    /// Evicts the single lowest-priority URL to make room for a new one, returning whether it
+    /// could. Frontiers with no priority notion (plain stacks/queues) can't and return `false`;
+    /// used by `State`'s `max_frontier` backpressure.
+    fn evict_lowest_priority(&mut self) -> bool {
+        false
+    }
+}
+
+/// Depth-first frontier, i.e. a plain stack. This is the sheduler's default and matches the
+/// traversal order the sheduler had before `Frontier` existed.
+#[derive(Debug, Default)]
+pub struct DepthFirstFrontier(Vec<Url>);
+
+impl Frontier for DepthFirstFrontier {
+    fn push(&mut self, url: Url, _depth: u32) {
+        self.0.push(url);
+    }
+
+    fn pop(&mut self) -> Option<Url> {
+        self.0.pop()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn drain(&mut self) -> Vec<Url> {
+        std::mem::take(&mut self.0)
+    }
+}
+
+/// Breadth-first frontier, a plain FIFO queue.
+#[derive(Debug, Default)]
+pub struct BreadthFirstFrontier(VecDeque<Url>);
+
+impl Frontier for BreadthFirstFrontier {
+    fn push(&mut self, url: Url, _depth: u32) {
+        self.0.push_back(url);
+    }
+
+    fn pop(&mut self) -> Option<Url> {
+        self.0.pop_front()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn drain(&mut self) -> Vec<Url> {
+        self.0.drain(..).collect()
+    }
+}
+
+/// A single priority-frontier entry. `seq` breaks ties between equal scores so that, much like
+/// cargo's `DependencyQueue`, equally-scored URLs still come out in the order they were pushed.
+#[derive(Debug)]
+struct PriorityEntry {
+    score: i64,
+    seq: u64,
+    url: Url,
+}
+
+impl PartialEq for PriorityEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.score == other.score && self.seq == other.seq
+    }
+}
+
+impl Eq for PriorityEntry {}
+
+impl PartialOrd for PriorityEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PriorityEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // BinaryHeap is a max-heap: higher score pops first, and on a tie the lower seq (the
+        // entry pushed earlier) should win, so its comparison is reversed.
+        self.score.cmp(&other.score).then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+type ScoreFn = Box<dyn Fn(&Url, u32) -> i64 + Send + Sync>;
+
+/// Priority frontier backed by a `BinaryHeap`, scoring each URL from its crawl depth via a
+/// caller-supplied closure so callers can e.g. prefer shallow pages or boost certain paths.
+pub struct PriorityFrontier {
+    heap: BinaryHeap<PriorityEntry>,
+    next_seq: u64,
+    score: ScoreFn,
+}
+
+impl PriorityFrontier {
+    pub fn new(score: impl Fn(&Url, u32) -> i64 + Send + Sync + 'static) -> Self {
+        PriorityFrontier {
+            heap: BinaryHeap::new(),
+            next_seq: 0,
+            score: Box::new(score),
+        }
+    }
+}
+
+impl fmt::Debug for PriorityFrontier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PriorityFrontier")
+            .field("heap", &self.heap)
+            .field("next_seq", &self.next_seq)
+            .finish()
+    }
+}
+
+impl Frontier for PriorityFrontier {
+    fn push(&mut self, url: Url, depth: u32) {
+        let score = (self.score)(&url, depth);
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.heap.push(PriorityEntry { score, seq, url });
+    }
+
+    fn pop(&mut self) -> Option<Url> {
+        self.heap.pop().map(|entry| entry.url)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    fn drain(&mut self) -> Vec<Url> {
+        self.heap.drain().map(|entry| entry.url).collect()
+    }
+
+    fn evict_lowest_priority(&mut self) -> bool {
+        // reuse PriorityEntry's own Ord (the same relation the heap pops by) rather than a
+        // hand-rolled comparator, so "lowest priority" here really means "pops last".
+        let Some((idx, _)) = self.heap.iter().enumerate().min_by(|(_, a), (_, b)| a.cmp(b)) else {
+            return false;
+        };
+
+        // BinaryHeap has no targeted removal, so rebuild it without the evicted entry; this
+        // only runs when the frontier is already at its cap, not on every push.
+        let mut entries: Vec<PriorityEntry> = self.heap.drain().collect();
+        entries.remove(idx);
+        self.heap = entries.into_iter().collect();
+        true
+    }
+}
+
+/// How long an engine should back off when it finds a host at its concurrency cap, since
+/// unlike an empty token bucket there's no known moment a slot will free up.
+const HOST_CONCURRENCY_RETRY: Duration = Duration::from_secs(1);
+
+/// How long an engine should back off when the frontier is at its `max_frontier` cap, longer
+/// than the default idle backoff to give the crawl time to drain the queue instead of polling
+/// a saturated sheduler in a tight loop.
+const SATURATED_FRONTIER_RETRY: Duration = Duration::from_secs(30);
+
+/// Per-host politeness budget: caps how many engines may be working a host at once, and rate
+/// limits dispatch via a token bucket (modeled on hyper's per-key connection pooling and
+/// cargo's jobserver token accounting).
+#[derive(Debug)]
+struct HostBudget {
+    max_concurrent: usize,
+    active: usize,
+    rate: f64,
+    capacity: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl HostBudget {
+    fn new(max_concurrent: usize, rate: f64, capacity: f64) -> Self {
+        HostBudget {
+            max_concurrent,
+            active: 0,
+            rate,
+            capacity,
+            tokens: capacity,
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.last_refill = now;
+    }
+
+    /// Tries to take one token and one concurrency slot, returning whether it succeeded.
+    fn try_acquire(&mut self) -> bool {
+        self.refill();
+        if self.active < self.max_concurrent && self.tokens >= 1.0 {
+            self.active += 1;
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn release(&mut self) {
+        self.active = self.active.saturating_sub(1);
+    }
+
+    /// How long until this host is worth retrying.
+    fn wait_hint(&self) -> Duration {
+        if self.active >= self.max_concurrent {
+            HOST_CONCURRENCY_RETRY
+        } else if self.tokens >= 1.0 {
+            Duration::from_secs(0)
+        } else if self.rate > 0.0 {
+            Duration::from_secs_f64((1.0 - self.tokens) / self.rate)
+        } else {
+            // a zero (or negative) rate never refills on its own
+            HOST_CONCURRENCY_RETRY
+        }
+    }
+}
+
 /// Sheduler responsible for providing engines with *work*
 ///
 /// Mainly the sheduler abstraction is developed in order to have an ability to identify that
@@ -34,23 +283,68 @@ use url::Url;
 /// We could check queeues but we could't guaranteee that some engine was doing work at the time.
 /// And it's results could expand a state queues.
 ///
-/// todo: do we need to develop a restore mechanism in case of engine error?
-/// now not becouse engine is responsible for its errors but?
+/// Crash recovery: a URL handed to an engine is tracked in `State::in_progress` until the
+/// engine calls back `complete_job`/`fail_job`. `reap_stalled` borrows hyper's pool liveness
+/// idea (`Poolable::is_open`) and requeues work held by an engine that hasn't been heard from
+/// within a timeout, so a dead engine can't lose a URL forever.
+///
+/// Politeness: URLs are also gated per-host (see `HostBudget`) so a crawl can't hammer a
+/// single domain even when many engines are free and the frontier is full of its links.
+///
+/// Scope: `allow_list`/`deny_list` (see `set_allow_list`/`set_deny_list`) filter URLs before
+/// they ever reach `seen_list`, and `same_domain_only` pins the crawl to whatever host(s) the
+/// first marked URLs were on.
+///
+/// Resumability: `snapshot`/`restore` checkpoint `State` to a `StateSnapshot` that can be
+/// serialized to JSON and reloaded, so a long crawl can survive a restart. `should_checkpoint`
+/// is a poll hook an engine loop can call every iteration to know when it's due.
+///
+/// Backpressure: `set_max_frontier` bounds how many URLs the frontier may hold at once (the
+/// pool-backpressure pattern behind hyper's `Pool`, applied to URLs instead of connections), so
+/// a crawl that discovers links faster than it visits them can't grow without bound.
 #[derive(Default)]
 pub struct Sheduler {
     engines: HashMap<i32, EngineState>,
+    last_seen: HashMap<i32, Instant>,
     state: State,
     engines_stoped: bool,
+    host_budgets: HashMap<String, HostBudget>,
+    default_host_limit: Option<(usize, f64, f64)>,
+    allow_list: Option<RegexSet>,
+    deny_list: Option<RegexSet>,
+    same_domain_only: bool,
+    seed_hosts: HashSet<String>,
+    last_checkpoint: Option<Instant>,
 }
 
-#[derive(Default, Debug)]
+#[derive(Debug)]
 pub struct State {
     seen_list: HashSet<Url>,
-    // in_progress: HashSet<Url>,
-    wait_list: Vec<Url>,
+    in_progress: HashSet<Url>,
+    // depth each seen url was first discovered at, so a child url's depth can be derived from
+    // its parent without the caller having to track it itself
+    depths: HashMap<Url, u32>,
+    frontier: Box<dyn Frontier>,
+    max_frontier: Option<usize>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        State::with_frontier(Box::new(DepthFirstFrontier::default()))
+    }
 }
 
 impl State {
+    pub fn with_frontier(frontier: Box<dyn Frontier>) -> Self {
+        State {
+            seen_list: HashSet::new(),
+            in_progress: HashSet::new(),
+            depths: HashMap::new(),
+            frontier,
+            max_frontier: None,
+        }
+    }
+
     pub fn update(&mut self, urls: Vec<Url>) {
         for url in urls {
             self.update_url(url)
@@ -58,11 +352,96 @@ impl State {
     }
 
     pub fn update_url(&mut self, url: Url) {
-        if !self.seen_list.contains(&url) {
-            self.wait_list.push(url.clone());
-            self.seen_list.insert(url);
+        self.insert_at_depth(url, 0);
+    }
+
+    pub fn update_child_url(&mut self, parent: &Url, url: Url) {
+        let depth = self.depths.get(parent).copied().unwrap_or(0) + 1;
+        self.insert_at_depth(url, depth);
+    }
+
+    fn insert_at_depth(&mut self, url: Url, depth: u32) {
+        if self.seen_list.contains(&url) {
+            return;
+        }
+        // at capacity: make room by evicting the lowest-priority entry if the frontier supports
+        // that (priority frontier), otherwise refuse the new url outright. seen_list still
+        // records it so it isn't re-crawled if rediscovered later.
+        if let Some(max) = self.max_frontier {
+            if self.frontier.len() >= max && !self.frontier.evict_lowest_priority() {
+                self.seen_list.insert(url);
+                return;
+            }
         }
+        self.depths.insert(url.clone(), depth);
+        self.frontier.push(url.clone(), depth);
+        self.seen_list.insert(url);
+    }
+
+    /// Caps how many URLs may wait in the frontier at once. See `Sheduler::set_max_frontier`
+    /// for what happens once the cap is hit.
+    pub fn set_max_frontier(&mut self, max: usize) {
+        self.max_frontier = Some(max);
     }
+
+    /// Whether the frontier is currently at its `max_frontier` cap (always `false` if no cap is
+    /// set).
+    pub fn is_saturated(&self) -> bool {
+        self.max_frontier.is_some_and(|max| self.frontier.len() >= max)
+    }
+
+    // requeues an already-seen url (e.g. after a failed job) at its originally recorded depth
+    fn requeue(&mut self, url: Url) {
+        let depth = self.depths.get(&url).copied().unwrap_or(0);
+        self.frontier.push(url, depth);
+    }
+
+    /// Captures `seen_list` and everything still pending in the frontier as a `StateSnapshot`.
+    /// Non-destructive: the frontier is drained and immediately refilled.
+    pub fn snapshot(&mut self) -> StateSnapshot {
+        let drained = self.frontier.drain();
+        let pending = drained
+            .into_iter()
+            .map(|url| {
+                let depth = self.depths.get(&url).copied().unwrap_or(0);
+                self.frontier.push(url.clone(), depth);
+                (url.to_string(), depth)
+            })
+            .collect();
+
+        StateSnapshot {
+            seen_list: self.seen_list.iter().map(Url::to_string).collect(),
+            pending,
+            max_frontier: self.max_frontier,
+        }
+    }
+
+    /// Rebuilds a `State` from a `StateSnapshot`, replaying its pending URLs into `frontier`.
+    pub fn restore(snapshot: StateSnapshot, frontier: Box<dyn Frontier>) -> Result<Self, url::ParseError> {
+        let mut state = State::with_frontier(frontier);
+        state.max_frontier = snapshot.max_frontier;
+        for raw in snapshot.seen_list {
+            state.seen_list.insert(Url::parse(&raw)?);
+        }
+        for (raw, depth) in snapshot.pending {
+            let url = Url::parse(&raw)?;
+            state.depths.insert(url.clone(), depth);
+            state.frontier.push(url, depth);
+        }
+        Ok(state)
+    }
+}
+
+/// A serializable snapshot of a `State`'s URL bookkeeping, suitable for checkpointing a long
+/// crawl to JSON on disk and resuming it later via `Sheduler::restore`. `Url`s are stored as
+/// plain strings since the frontier itself (a `Box<dyn Frontier>`) can't be serialized directly.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StateSnapshot {
+    seen_list: Vec<String>,
+    // (url, depth) pairs still waiting to be crawled, including anything that was in_progress
+    // at snapshot time so a restart doesn't lose work an engine hadn't finished yet.
+    pending: Vec<(String, u32)>,
+    max_frontier: Option<usize>,
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -73,15 +452,25 @@ pub enum Job {
 }
 
 // todo: might engine initiate a close?
-#[derive(PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq)]
 pub enum EngineState {
     Idle,
-    // could hold a URL for recovery if there would be an error
-    Work,
+    // holds the URL being searched so it can be recovered if the engine fails or stalls
+    Work(Url),
     Created,
 }
 
 impl Sheduler {
+    /// Builds a sheduler backed by the given `Frontier`, e.g. `BreadthFirstFrontier::default()`
+    /// for a BFS crawl or `PriorityFrontier::new(score_fn)` for a scored crawl. The default
+    /// (depth-first) sheduler can still be built with `Sheduler::default()`.
+    pub fn with_frontier(frontier: Box<dyn Frontier>) -> Self {
+        Sheduler {
+            state: State::with_frontier(frontier),
+            ..Default::default()
+        }
+    }
+
     pub fn get_job(&mut self, engine_id: i32) -> Job {
         // todo: does this method is too compex?
         // keeping a lock for too long is might a design smell
@@ -91,36 +480,269 @@ impl Sheduler {
         }
 
         if self.engines.iter().all(|(_, s)| s == &EngineState::Idle)
-            && self.state.wait_list.is_empty()
+            && self.state.frontier.is_empty()
+            && self.state.in_progress.is_empty()
         {
             self.close();
             return Job::Closed;
         }
 
-        let url = self.state.wait_list.pop();
+        // an engine asking for work again is itself the completion signal for whatever it was
+        // last handed: it wouldn't be here if it were still searching, and it would have called
+        // `fail_job` instead if the search had failed.
+        self.complete_job(engine_id);
+
+        // skip urls whose host is over its politeness budget, requeuing them instead of
+        // handing them out; a host with no explicit budget and no default policy is unlimited.
+        let mut deferred = Vec::new();
+        let mut wait_hints = Vec::new();
+        let url = loop {
+            match self.state.frontier.pop() {
+                None => break None,
+                Some(url) => {
+                    let host = url.host_str().unwrap_or("").to_owned();
+                    if self.try_acquire_host(&host) {
+                        break Some(url);
+                    }
+                    if let Some(budget) = self.host_budgets.get(&host) {
+                        wait_hints.push(budget.wait_hint());
+                    }
+                    deferred.push(url);
+                }
+            }
+        };
+        for url in deferred {
+            self.state.requeue(url);
+        }
+
         match url {
             Some(url) => {
-                self.set_engine_state(engine_id, EngineState::Work);
+                self.state.in_progress.insert(url.clone());
+                self.set_engine_state(engine_id, EngineState::Work(url.clone()));
                 Job::Search(url)
             }
             None => {
                 self.set_engine_state(engine_id, EngineState::Idle);
-                // todo: some logic with dynamic duration?
-                Job::Idle(Duration::from_millis(5000))
+                let duration = if self.state.is_saturated() {
+                    SATURATED_FRONTIER_RETRY
+                } else {
+                    wait_hints.into_iter().min().unwrap_or(Duration::from_millis(5000))
+                };
+                Job::Idle(duration)
+            }
+        }
+    }
+
+    /// Called by an engine once it successfully finished a `Job::Search`, dropping the URL
+    /// from `in_progress` so the crawl is free to close once nothing else is outstanding.
+    pub fn complete_job(&mut self, engine_id: i32) {
+        if let Some(EngineState::Work(url)) = self.engines.get(&engine_id).cloned() {
+            self.state.in_progress.remove(&url);
+            self.release_host(url.host_str().unwrap_or(""));
+        }
+        self.set_engine_state(engine_id, EngineState::Idle);
+    }
+
+    /// Called by an engine that failed a `Job::Search`, putting its URL back onto the frontier
+    /// instead of losing it.
+    pub fn fail_job(&mut self, engine_id: i32) {
+        if let Some(EngineState::Work(url)) = self.engines.get(&engine_id).cloned() {
+            self.state.in_progress.remove(&url);
+            self.release_host(url.host_str().unwrap_or(""));
+            self.state.requeue(url);
+        }
+        self.set_engine_state(engine_id, EngineState::Idle);
+    }
+
+    /// Sets a per-host politeness budget: at most `max_concurrent` engines may work `host` at
+    /// once, and dispatch is rate limited by a token bucket refilling at `rate` tokens/second
+    /// up to `capacity` tokens.
+    pub fn set_host_limit(&mut self, host: impl Into<String>, max_concurrent: usize, rate: f64, capacity: f64) {
+        self.host_budgets
+            .insert(host.into(), HostBudget::new(max_concurrent, rate, capacity));
+    }
+
+    /// Sets the politeness budget applied to any host without its own `set_host_limit` entry.
+    pub fn set_default_host_limit(&mut self, max_concurrent: usize, rate: f64, capacity: f64) {
+        self.default_host_limit = Some((max_concurrent, rate, capacity));
+    }
+
+    /// Caps how many URLs may wait in the frontier at once. Once the cap is hit, a new URL
+    /// either evicts the frontier's lowest-priority entry to make room (priority frontier only)
+    /// or is refused outright; `seen_list` stays authoritative either way, so a refused URL
+    /// isn't re-crawled if it's rediscovered later.
+    pub fn set_max_frontier(&mut self, max: usize) {
+        self.state.set_max_frontier(max);
+    }
+
+    /// Whether the frontier is currently at its `max_frontier` cap (always `false` if no cap is
+    /// set).
+    pub fn is_saturated(&self) -> bool {
+        self.state.is_saturated()
+    }
+
+    fn try_acquire_host(&mut self, host: &str) -> bool {
+        if !self.host_budgets.contains_key(host) {
+            let Some((max_concurrent, rate, capacity)) = self.default_host_limit else {
+                return true;
+            };
+            self.host_budgets
+                .insert(host.to_owned(), HostBudget::new(max_concurrent, rate, capacity));
+        }
+        self.host_budgets.get_mut(host).unwrap().try_acquire()
+    }
+
+    fn release_host(&mut self, host: &str) {
+        if let Some(budget) = self.host_budgets.get_mut(host) {
+            budget.release();
+        }
+    }
+
+    /// Requeues URLs held by engines that haven't reported in within `timeout`, recovering
+    /// from an engine that died mid-`Job::Search` instead of stalling the crawl forever.
+    ///
+    /// `last_seen` only updates on handout/completion, not on any in-progress heartbeat from the
+    /// engine, so `timeout` must be picked conservatively (comfortably longer than a single
+    /// page's real processing time) or a URL still genuinely in flight gets duplicate-dispatched.
+    pub fn reap_stalled(&mut self, timeout: Duration) {
+        let now = Instant::now();
+        let stalled: Vec<i32> = self
+            .last_seen
+            .iter()
+            .filter(|(_, &seen)| now.duration_since(seen) > timeout)
+            .map(|(&id, _)| id)
+            .collect();
+
+        for id in stalled {
+            self.fail_job(id);
+        }
+    }
+
+    /// Checkpoints the crawl to a `StateSnapshot`, folding any URL an engine is mid-search on
+    /// (`in_progress`) back into the pending list so a later `restore` can't lose it.
+    pub fn snapshot(&mut self) -> StateSnapshot {
+        let mut snapshot = self.state.snapshot();
+        for url in &self.state.in_progress {
+            let depth = self.state.depths.get(url).copied().unwrap_or(0);
+            snapshot.pending.push((url.to_string(), depth));
+        }
+        snapshot
+    }
+
+    /// Rebuilds a sheduler from a `StateSnapshot` and a `frontier` (typically the same kind the
+    /// crawl used before, e.g. via `with_frontier`). Engines all start fresh: nothing is still
+    /// `Work`ing or `in_progress` after a restore.
+    pub fn restore(snapshot: StateSnapshot, frontier: Box<dyn Frontier>) -> Result<Self, url::ParseError> {
+        Ok(Sheduler {
+            state: State::restore(snapshot, frontier)?,
+            ..Default::default()
+        })
+    }
+
+    /// Polls whether it's time to checkpoint again, tracking the last checkpoint internally so
+    /// an engine loop can just call this every iteration: `if sheduler.should_checkpoint(every)
+    /// { write sheduler.snapshot() to disk }`.
+    pub fn should_checkpoint(&mut self, every: Duration) -> bool {
+        let now = Instant::now();
+        let due = match self.last_checkpoint {
+            None => true,
+            Some(last) => now.duration_since(last) >= every,
+        };
+        if due {
+            self.last_checkpoint = Some(now);
+        }
+        due
+    }
+
+    /// Restricts the crawl to URLs matching at least one of `patterns`. Passing an empty list
+    /// clears the allow-list (the default: every URL is allowed unless denied).
+    pub fn set_allow_list<I, S>(&mut self, patterns: I) -> Result<(), regex::Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let allow_list = RegexSet::new(patterns)?;
+        // an empty set matches nothing, but an absent allow-list should allow everything, so
+        // treat the two as distinct: zero patterns clears the allow-list instead of rejecting
+        // every URL.
+        self.allow_list = if allow_list.is_empty() { None } else { Some(allow_list) };
+        Ok(())
+    }
+
+    /// Excludes URLs matching any of `patterns` from the crawl, even if they'd otherwise pass
+    /// the allow-list.
+    pub fn set_deny_list<I, S>(&mut self, patterns: I) -> Result<(), regex::Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.deny_list = Some(RegexSet::new(patterns)?);
+        Ok(())
+    }
+
+    /// Pins the crawl to the host(s) of the first URLs marked after this is enabled (for a
+    /// `mark_urls` batch, every URL in it, before the allow/deny lists are applied); any URL on
+    /// a different host is rejected from then on.
+    pub fn set_same_domain_only(&mut self, enabled: bool) {
+        self.same_domain_only = enabled;
+    }
+
+    /// Checks `url` against the allow-list, deny-list and same-domain constraint, recording its
+    /// host as a seed host if same-domain restriction is on and no seed host is set yet.
+    fn is_in_scope(&mut self, url: &Url) -> bool {
+        let href = url.as_str();
+        if self.deny_list.as_ref().is_some_and(|deny| deny.is_match(href)) {
+            return false;
+        }
+        if self.allow_list.as_ref().is_some_and(|allow| !allow.is_match(href)) {
+            return false;
+        }
+        if self.same_domain_only {
+            let host = url.host_str().unwrap_or("").to_owned();
+            if self.seed_hosts.is_empty() {
+                self.seed_hosts.insert(host);
+            } else if !self.seed_hosts.contains(&host) {
+                return false;
             }
         }
+        true
     }
 
     pub fn mark_urls(&mut self, urls: Vec<Url>) {
-        self.state.update(urls);
+        // same_domain_only pins to the hosts of the *whole* initial seed batch, not whichever
+        // url in it happens to be processed first: register them all up front so later urls in
+        // this same batch aren't mistaken for off-domain discoveries and dropped.
+        if self.same_domain_only && self.seed_hosts.is_empty() {
+            self.seed_hosts
+                .extend(urls.iter().map(|url| url.host_str().unwrap_or("").to_owned()));
+        }
+        for url in urls {
+            self.mark_url(url);
+        }
     }
 
     pub fn mark_url(&mut self, url: Url) {
-        self.state.update_url(url);
+        if self.is_in_scope(&url) {
+            self.state.update_url(url);
+        }
+    }
+
+    /// Marks URLs discovered while processing `parent`, threading `parent`'s depth + 1 through
+    /// to the frontier (so e.g. the priority frontier can prefer shallower pages).
+    pub fn mark_child_urls(&mut self, parent: &Url, urls: Vec<Url>) {
+        for url in urls {
+            self.mark_child_url(parent, url);
+        }
+    }
+
+    pub fn mark_child_url(&mut self, parent: &Url, url: Url) {
+        if self.is_in_scope(&url) {
+            self.state.update_child_url(parent, url);
+        }
     }
 
     pub fn is_closed(&self) -> bool {
-        self.engines_stoped
+        self.engines_stoped && self.state.in_progress.is_empty()
     }
 
     pub fn close(&mut self) {
@@ -128,13 +750,14 @@ impl Sheduler {
     }
 
     pub(crate) fn set_engine_state(&mut self, id: i32, state: EngineState) {
+        self.last_seen.insert(id, Instant::now());
         self.engines.insert(id, state);
     }
 }
 
 #[cfg(test)]
 mod sheduler_tests {
-    use super::{Job, Sheduler};
+    use super::{BreadthFirstFrontier, DepthFirstFrontier, Job, PriorityFrontier, Sheduler, StateSnapshot};
     use std::time::Duration;
     use url::Url;
 
@@ -239,4 +862,376 @@ mod sheduler_tests {
         assert_eq!(sheduler.get_job(1), Job::Closed);
         assert_eq!(sheduler.get_job(2000), Job::Closed);
     }
+
+    #[test]
+    fn in_progress_blocks_close_test() {
+        let url = Url::parse("http://locahost:8080").unwrap();
+
+        let mut sheduler = Sheduler::default();
+        sheduler.mark_url(url.clone());
+
+        assert_eq!(sheduler.get_job(0), Job::Search(url));
+        // the frontier is empty and the only engine is "working", but the url is still
+        // in_progress, so the crawl must not close yet.
+        assert_eq!(sheduler.get_job(1), Job::Idle(Duration::from_secs(5)));
+
+        sheduler.complete_job(0);
+
+        assert_eq!(sheduler.get_job(0), Job::Closed);
+    }
+
+    #[test]
+    fn fail_job_requeues_url_test() {
+        let url = Url::parse("http://locahost:8080").unwrap();
+
+        let mut sheduler = Sheduler::default();
+        sheduler.mark_url(url.clone());
+
+        assert_eq!(sheduler.get_job(0), Job::Search(url.clone()));
+
+        sheduler.fail_job(0);
+
+        assert_eq!(sheduler.get_job(1), Job::Search(url));
+        assert_eq!(sheduler.get_job(0), Job::Idle(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn reap_stalled_requeues_url_test() {
+        let url = Url::parse("http://locahost:8080").unwrap();
+
+        let mut sheduler = Sheduler::default();
+        sheduler.mark_url(url.clone());
+
+        assert_eq!(sheduler.get_job(0), Job::Search(url.clone()));
+
+        sheduler.reap_stalled(Duration::from_secs(0));
+
+        assert_eq!(sheduler.get_job(1), Job::Search(url));
+    }
+
+    #[test]
+    fn breadth_first_frontier_test() {
+        let urls = vec![
+            Url::parse("http://locahost:8080").unwrap(),
+            Url::parse("http://0.0.0.0:8080").unwrap(),
+        ];
+
+        let mut sheduler = Sheduler::with_frontier(Box::new(BreadthFirstFrontier::default()));
+        sheduler.mark_urls(urls.clone());
+
+        // FIFO: the first url marked is the first one handed out.
+        assert_eq!(sheduler.get_job(0), Job::Search(urls[0].clone()));
+        assert_eq!(sheduler.get_job(0), Job::Search(urls[1].clone()));
+        assert_eq!(sheduler.get_job(0), Job::Idle(Duration::from_secs(5)));
+        assert_eq!(sheduler.get_job(0), Job::Closed);
+    }
+
+    #[test]
+    fn priority_frontier_prefers_shallow_urls_test() {
+        let shallow = Url::parse("http://locahost:8080/shallow").unwrap();
+        let deep = Url::parse("http://locahost:8080/deep").unwrap();
+
+        // lower depth -> higher score -> popped first
+        let mut sheduler =
+            Sheduler::with_frontier(Box::new(PriorityFrontier::new(|_url, depth| -(depth as i64))));
+        sheduler.mark_child_url(&deep, deep.clone());
+        sheduler.mark_url(shallow.clone());
+
+        assert_eq!(sheduler.get_job(0), Job::Search(shallow));
+        assert_eq!(sheduler.get_job(0), Job::Search(deep));
+    }
+
+    #[test]
+    fn mark_child_url_threads_parent_depth_test() {
+        let parent = Url::parse("http://locahost:8080").unwrap();
+        let child = Url::parse("http://locahost:8080/child").unwrap();
+        let grandchild = Url::parse("http://locahost:8080/child/grandchild").unwrap();
+
+        // depth-0 urls get score 0, depth-1 get -1, depth-2 get -2: shallower pops first.
+        let mut sheduler =
+            Sheduler::with_frontier(Box::new(PriorityFrontier::new(|_url, depth| -(depth as i64))));
+        sheduler.mark_url(parent.clone());
+        sheduler.mark_child_url(&parent, child.clone());
+        sheduler.mark_child_url(&child, grandchild.clone());
+
+        assert_eq!(sheduler.get_job(0), Job::Search(parent));
+        assert_eq!(sheduler.get_job(0), Job::Search(child));
+        assert_eq!(sheduler.get_job(0), Job::Search(grandchild));
+    }
+
+    #[test]
+    fn host_concurrency_cap_defers_second_engine_test() {
+        let urls = vec![
+            Url::parse("http://locahost:8080/a").unwrap(),
+            Url::parse("http://locahost:8080/b").unwrap(),
+        ];
+
+        let mut sheduler = Sheduler::default();
+        sheduler.set_host_limit("locahost", 1, 100.0, 10.0);
+        sheduler.mark_urls(urls.clone());
+
+        assert_eq!(sheduler.get_job(0), Job::Search(urls[1].clone()));
+        // locahost is already at its cap of 1 concurrent engine, so urls[0] is deferred.
+        assert_eq!(sheduler.get_job(1), Job::Idle(Duration::from_secs(1)));
+
+        sheduler.complete_job(0);
+
+        assert_eq!(sheduler.get_job(1), Job::Search(urls[0].clone()));
+    }
+
+    #[test]
+    fn host_token_bucket_defers_second_engine_test() {
+        let urls = vec![
+            Url::parse("http://locahost:8080/a").unwrap(),
+            Url::parse("http://locahost:8080/b").unwrap(),
+        ];
+
+        let mut sheduler = Sheduler::default();
+        // a single token that never refills: only one dispatch is ever allowed on this host.
+        sheduler.set_host_limit("locahost", 10, 0.0, 1.0);
+        sheduler.mark_urls(urls.clone());
+
+        assert_eq!(sheduler.get_job(0), Job::Search(urls[1].clone()));
+        assert_eq!(sheduler.get_job(1), Job::Idle(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn deny_list_rejects_matching_url_test() {
+        let allowed = Url::parse("http://locahost:8080/ok").unwrap();
+        let denied = Url::parse("http://locahost:8080/admin").unwrap();
+
+        let mut sheduler = Sheduler::default();
+        sheduler.set_deny_list(["/admin"]).unwrap();
+        sheduler.mark_urls(vec![allowed.clone(), denied]);
+
+        assert_eq!(sheduler.get_job(0), Job::Search(allowed));
+        assert_eq!(sheduler.get_job(0), Job::Idle(Duration::from_secs(5)));
+        assert_eq!(sheduler.get_job(0), Job::Closed);
+    }
+
+    #[test]
+    fn allow_list_rejects_non_matching_url_test() {
+        let allowed = Url::parse("http://locahost:8080/blog/post").unwrap();
+        let rejected = Url::parse("http://locahost:8080/login").unwrap();
+
+        let mut sheduler = Sheduler::default();
+        sheduler.set_allow_list([r"^http://locahost:8080/blog/"]).unwrap();
+        sheduler.mark_urls(vec![allowed.clone(), rejected]);
+
+        assert_eq!(sheduler.get_job(0), Job::Search(allowed));
+        assert_eq!(sheduler.get_job(0), Job::Idle(Duration::from_secs(5)));
+        assert_eq!(sheduler.get_job(0), Job::Closed);
+    }
+
+    #[test]
+    fn empty_allow_list_clears_it_instead_of_rejecting_everything_test() {
+        let url = Url::parse("http://locahost:8080/page").unwrap();
+
+        let mut sheduler = Sheduler::default();
+        sheduler.set_allow_list(Vec::<&str>::new()).unwrap();
+        sheduler.mark_url(url.clone());
+
+        assert_eq!(sheduler.get_job(0), Job::Search(url));
+    }
+
+    #[test]
+    fn same_domain_only_pins_to_first_seed_batch_host_test() {
+        let on_domain = Url::parse("http://locahost:8080/page").unwrap();
+        let off_domain = Url::parse("http://0.0.0.0:8080/page").unwrap();
+
+        let mut sheduler = Sheduler::default();
+        sheduler.set_same_domain_only(true);
+        sheduler.mark_urls(vec![on_domain.clone()]);
+        // off_domain arrives in a later batch, once the seed host is already pinned.
+        sheduler.mark_urls(vec![off_domain]);
+
+        assert_eq!(sheduler.get_job(0), Job::Search(on_domain));
+        assert_eq!(sheduler.get_job(0), Job::Idle(Duration::from_secs(5)));
+        assert_eq!(sheduler.get_job(0), Job::Closed);
+    }
+
+    #[test]
+    fn same_domain_only_accepts_every_host_in_the_initial_seed_batch_test() {
+        let seed_a = Url::parse("http://locahost:8080/page").unwrap();
+        let seed_b = Url::parse("http://0.0.0.0:8080/page").unwrap();
+        let off_domain = Url::parse("http://8.8.8.8:60/page").unwrap();
+
+        let mut sheduler = Sheduler::default();
+        sheduler.set_same_domain_only(true);
+        // both seeds are in the same initial batch, so both of their hosts get pinned, not just
+        // whichever one happens to be processed first.
+        sheduler.mark_urls(vec![seed_a.clone(), seed_b.clone()]);
+        sheduler.mark_urls(vec![off_domain]);
+
+        assert_eq!(sheduler.get_job(0), Job::Search(seed_b));
+        assert_eq!(sheduler.get_job(0), Job::Search(seed_a));
+        assert_eq!(sheduler.get_job(0), Job::Idle(Duration::from_secs(5)));
+        assert_eq!(sheduler.get_job(0), Job::Closed);
+    }
+
+    #[test]
+    fn default_host_limit_applies_without_explicit_entry_test() {
+        let urls = vec![
+            Url::parse("http://locahost:8080/a").unwrap(),
+            Url::parse("http://locahost:8080/b").unwrap(),
+        ];
+
+        let mut sheduler = Sheduler::default();
+        sheduler.set_default_host_limit(1, 100.0, 10.0);
+        sheduler.mark_urls(urls.clone());
+
+        assert_eq!(sheduler.get_job(0), Job::Search(urls[1].clone()));
+        assert_eq!(sheduler.get_job(1), Job::Idle(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn snapshot_restore_round_trip_test() {
+        let urls = vec![
+            Url::parse("http://locahost:8080/a").unwrap(),
+            Url::parse("http://locahost:8080/b").unwrap(),
+        ];
+
+        let mut sheduler = Sheduler::default();
+        sheduler.mark_urls(urls.clone());
+
+        let snapshot = sheduler.snapshot();
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let restored_snapshot: StateSnapshot = serde_json::from_str(&json).unwrap();
+
+        let mut restored =
+            Sheduler::restore(restored_snapshot, Box::new(DepthFirstFrontier::default())).unwrap();
+
+        assert_eq!(restored.get_job(0), Job::Search(urls[1].clone()));
+        assert_eq!(restored.get_job(0), Job::Search(urls[0].clone()));
+        assert_eq!(restored.get_job(0), Job::Idle(Duration::from_secs(5)));
+        assert_eq!(restored.get_job(0), Job::Closed);
+    }
+
+    #[test]
+    fn restore_folds_in_progress_back_into_frontier_test() {
+        let url = Url::parse("http://locahost:8080").unwrap();
+
+        let mut sheduler = Sheduler::default();
+        sheduler.mark_url(url.clone());
+        assert_eq!(sheduler.get_job(0), Job::Search(url.clone()));
+
+        // engine 0 never calls complete_job/fail_job before the snapshot is taken.
+        let snapshot = sheduler.snapshot();
+        let mut restored = Sheduler::restore(snapshot, Box::new(DepthFirstFrontier::default())).unwrap();
+
+        assert_eq!(restored.get_job(0), Job::Search(url));
+    }
+
+    #[test]
+    fn restore_preserves_max_frontier_cap_test() {
+        let url = Url::parse("http://locahost:8080").unwrap();
+
+        let mut sheduler = Sheduler::default();
+        sheduler.set_max_frontier(1);
+        sheduler.mark_url(url);
+        assert!(sheduler.is_saturated());
+
+        let snapshot = sheduler.snapshot();
+        let restored = Sheduler::restore(snapshot, Box::new(DepthFirstFrontier::default())).unwrap();
+
+        assert!(restored.is_saturated());
+    }
+
+    #[test]
+    fn should_checkpoint_test() {
+        let mut sheduler = Sheduler::default();
+
+        assert!(sheduler.should_checkpoint(Duration::from_secs(60)));
+        assert!(!sheduler.should_checkpoint(Duration::from_secs(60)));
+        assert!(sheduler.should_checkpoint(Duration::from_secs(0)));
+    }
+
+    #[test]
+    fn max_frontier_refuses_new_urls_once_full_test() {
+        let a = Url::parse("http://locahost:8080/a").unwrap();
+        let b = Url::parse("http://locahost:8080/b").unwrap();
+        let dropped = Url::parse("http://locahost:8080/dropped").unwrap();
+
+        let mut sheduler = Sheduler::default();
+        sheduler.set_max_frontier(2);
+
+        sheduler.mark_url(a.clone());
+        assert!(!sheduler.is_saturated());
+
+        sheduler.mark_url(b.clone());
+        assert!(sheduler.is_saturated());
+
+        // the frontier is already at its cap of 2, so this one is refused outright.
+        sheduler.mark_url(dropped);
+        assert!(sheduler.is_saturated());
+
+        // LIFO: b was pushed last.
+        assert_eq!(sheduler.get_job(0), Job::Search(b));
+        assert_eq!(sheduler.get_job(0), Job::Search(a));
+        assert_eq!(sheduler.get_job(0), Job::Idle(Duration::from_secs(5)));
+        assert_eq!(sheduler.get_job(0), Job::Closed);
+    }
+
+    #[test]
+    fn max_frontier_evicts_lowest_priority_to_make_room_test() {
+        let low = Url::parse("http://locahost:8080/low").unwrap();
+        let mid = Url::parse("http://locahost:8080/mid").unwrap();
+        let high = Url::parse("http://locahost:8080/high").unwrap();
+
+        let score = |url: &Url, _depth: u32| match url.path() {
+            "/high" => 2,
+            "/mid" => 1,
+            _ => 0,
+        };
+        let mut sheduler = Sheduler::with_frontier(Box::new(PriorityFrontier::new(score)));
+        sheduler.set_max_frontier(2);
+
+        sheduler.mark_url(low);
+        sheduler.mark_url(mid.clone());
+        assert!(sheduler.is_saturated());
+
+        // the frontier is full, so `low` (the lowest score) is evicted to make room for `high`.
+        sheduler.mark_url(high.clone());
+        assert!(sheduler.is_saturated());
+
+        assert_eq!(sheduler.get_job(0), Job::Search(high));
+        assert_eq!(sheduler.get_job(0), Job::Search(mid));
+        assert_eq!(sheduler.get_job(0), Job::Idle(Duration::from_secs(5)));
+        assert_eq!(sheduler.get_job(0), Job::Closed);
+    }
+
+    #[test]
+    fn max_frontier_evicts_later_pushed_entry_on_score_tie_test() {
+        // x and y score equally, so on a tie x (pushed first) is the one that pops first and y
+        // is the one that should be evicted to make room for z.
+        let x = Url::parse("http://locahost:8080/x").unwrap();
+        let y = Url::parse("http://locahost:8080/y").unwrap();
+        let z = Url::parse("http://locahost:8080/z").unwrap();
+
+        let mut sheduler = Sheduler::with_frontier(Box::new(PriorityFrontier::new(|_url, _depth| 0)));
+        sheduler.set_max_frontier(2);
+
+        sheduler.mark_url(x.clone());
+        sheduler.mark_url(y);
+        sheduler.mark_url(z.clone());
+
+        assert_eq!(sheduler.get_job(0), Job::Search(x));
+        assert_eq!(sheduler.get_job(0), Job::Search(z));
+        assert_eq!(sheduler.get_job(0), Job::Idle(Duration::from_secs(5)));
+        assert_eq!(sheduler.get_job(0), Job::Closed);
+    }
+
+    #[test]
+    fn get_job_uses_longer_idle_backoff_while_saturated_test() {
+        let url = Url::parse("http://locahost:8080").unwrap();
+
+        let mut sheduler = Sheduler::default();
+        sheduler.set_max_frontier(1);
+        // nothing on this host is ever dispatchable, so the url stays stuck in the frontier.
+        sheduler.set_host_limit("locahost", 0, 0.0, 0.0);
+        sheduler.mark_url(url);
+
+        assert!(sheduler.is_saturated());
+        assert_eq!(sheduler.get_job(0), Job::Idle(Duration::from_secs(30)));
+    }
 }